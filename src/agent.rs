@@ -0,0 +1,266 @@
+//! Autonomous creature agents that crawl over the terrain: A* pathfinding
+//! toward food, and a decaying pheromone trail they lay down on the way out
+//! and follow back home, ant-colony style.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::registry::{ElementId, ElementRegistry};
+use crate::sim::{Coordinate, ElementMatrix, ARR_HEIGHT, ARR_WIDTH};
+
+/// Caps how much work a single A* search can do per agent per tick, so a
+/// food cell with no reachable path doesn't stall the whole simulation.
+const MAX_EXPANDED_NODES: usize = 2_000;
+
+const PHEROMONE_DEPOSIT: f32 = 1.0;
+const PHEROMONE_DECAY: f32 = 0.995;
+const PHEROMONE_DIFFUSION: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AgentGoal {
+    /// Walking toward the nearest food cell, laying down pheromone.
+    Seek,
+    /// Walking back toward home by following the pheromone gradient.
+    Return,
+}
+
+pub(crate) struct Agent {
+    pub(crate) pos: Coordinate,
+    pub(crate) goal: AgentGoal,
+    home: Coordinate,
+    /// Remaining steps of the current `Seek` path, goal-first so the next
+    /// step is a cheap `pop()`.
+    path: Vec<Coordinate>,
+}
+
+impl Agent {
+    pub(crate) fn new(pos: Coordinate) -> Self {
+        Self {
+            pos,
+            goal: AgentGoal::Seek,
+            home: pos,
+            path: Vec::new(),
+        }
+    }
+}
+
+/// A decaying, diffusing scalar field over the grid that `Return`-goal
+/// agents climb to find their way home.
+pub(crate) struct PheromoneMap {
+    values: Vec<f32>,
+}
+
+impl PheromoneMap {
+    pub(crate) fn new() -> Self {
+        Self {
+            values: vec![0.0; ARR_WIDTH * ARR_HEIGHT],
+        }
+    }
+
+    fn deposit(&mut self, at: Coordinate, amount: f32) {
+        self.values[usize::from(at)] += amount;
+    }
+
+    fn at(&self, coord: Coordinate) -> f32 {
+        self.values[usize::from(coord)]
+    }
+
+    /// Decay every value, spreading a fraction of it into the 4-neighborhood
+    /// first so trails blur and fade instead of staying pin-sharp.
+    pub(crate) fn tick(&mut self) {
+        let mut next = self.values.clone();
+        for y in 0..ARR_HEIGHT {
+            for x in 0..ARR_WIDTH {
+                let coord = Coordinate { x, y };
+                let value = self.values[usize::from(coord)];
+                if value <= f32::EPSILON {
+                    continue;
+                }
+                let spread = value * PHEROMONE_DIFFUSION;
+                let neighbors = orthogonal_neighbors(coord);
+                next[usize::from(coord)] -= spread * neighbors.len() as f32;
+                for neighbor in neighbors {
+                    next[usize::from(neighbor)] += spread;
+                }
+            }
+        }
+        for value in &mut next {
+            *value *= PHEROMONE_DECAY;
+        }
+        self.values = next;
+    }
+}
+
+fn orthogonal_neighbors(coord: Coordinate) -> Vec<Coordinate> {
+    let mut neighbors = Vec::with_capacity(4);
+    if coord.x > 0 {
+        neighbors.push(Coordinate { x: coord.x - 1, y: coord.y });
+    }
+    if coord.x + 1 < ARR_WIDTH {
+        neighbors.push(Coordinate { x: coord.x + 1, y: coord.y });
+    }
+    if coord.y > 0 {
+        neighbors.push(Coordinate { x: coord.x, y: coord.y - 1 });
+    }
+    if coord.y + 1 < ARR_HEIGHT {
+        neighbors.push(Coordinate { x: coord.x, y: coord.y + 1 });
+    }
+    neighbors
+}
+
+fn manhattan(a: Coordinate, b: Coordinate) -> usize {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+}
+
+fn is_walkable(matrix: &ElementMatrix, registry: &ElementRegistry, coord: Coordinate, goal: Coordinate) -> bool {
+    coord == goal
+        || match &matrix.arr[coord] {
+            None => true,
+            Some(element) => registry.is_fluid(element.flavor),
+        }
+}
+
+#[derive(PartialEq, Eq)]
+struct OpenNode {
+    coord: Coordinate,
+    f: usize,
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap; reverse so the lowest f comes out first.
+        other.f.cmp(&self.f)
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A* search from `start` to `goal` over the passable cells of `matrix`.
+/// Empty cells and every element with moves of its own are floor; only
+/// static elements such as `Stone` are walls. `Sand` has moves, so it is
+/// floor like `Water`, not a wall. `goal` itself is always walkable, since
+/// it's the food cell the agent is trying to stand on.
+fn find_path(
+    matrix: &ElementMatrix,
+    registry: &ElementRegistry,
+    start: Coordinate,
+    goal: Coordinate,
+) -> Option<Vec<Coordinate>> {
+    let mut open = BinaryHeap::new();
+    open.push(OpenNode { coord: start, f: manhattan(start, goal) });
+
+    let mut came_from: HashMap<Coordinate, Coordinate> = HashMap::new();
+    let mut g_score: HashMap<Coordinate, usize> = HashMap::new();
+    g_score.insert(start, 0);
+
+    let mut expanded = 0;
+    while let Some(OpenNode { coord, .. }) = open.pop() {
+        if coord == goal {
+            return Some(reconstruct_path(&came_from, coord));
+        }
+
+        expanded += 1;
+        if expanded > MAX_EXPANDED_NODES {
+            return None;
+        }
+
+        for neighbor in orthogonal_neighbors(coord) {
+            if !is_walkable(matrix, registry, neighbor, goal) {
+                continue;
+            }
+
+            let tentative_g = g_score[&coord] + 1;
+            if tentative_g < *g_score.get(&neighbor).unwrap_or(&usize::MAX) {
+                came_from.insert(neighbor, coord);
+                g_score.insert(neighbor, tentative_g);
+                open.push(OpenNode { coord: neighbor, f: tentative_g + manhattan(neighbor, goal) });
+            }
+        }
+    }
+
+    None
+}
+
+fn reconstruct_path(came_from: &HashMap<Coordinate, Coordinate>, mut coord: Coordinate) -> Vec<Coordinate> {
+    let mut path = vec![coord];
+    while let Some(&prev) = came_from.get(&coord) {
+        path.push(prev);
+        coord = prev;
+    }
+    path
+}
+
+fn nearest_food(matrix: &ElementMatrix, from: Coordinate, food_id: ElementId) -> Option<Coordinate> {
+    (0..ARR_WIDTH * ARR_HEIGHT)
+        .map(Coordinate::from)
+        .filter(|&coord| matches!(&matrix.arr[coord], Some(element) if element.flavor == food_id))
+        .min_by_key(|&coord| manhattan(from, coord))
+}
+
+fn best_gradient_step(
+    matrix: &ElementMatrix,
+    registry: &ElementRegistry,
+    pheromones: &PheromoneMap,
+    pos: Coordinate,
+    home: Coordinate,
+) -> Option<Coordinate> {
+    orthogonal_neighbors(pos)
+        .into_iter()
+        .filter(|&neighbor| is_walkable(matrix, registry, neighbor, home))
+        .max_by(|&a, &b| {
+            pheromones
+                .at(a)
+                .total_cmp(&pheromones.at(b))
+                .then_with(|| manhattan(b, home).cmp(&manhattan(a, home)))
+        })
+}
+
+/// Step every agent one tick: `Seek` agents (re)plan an A* path to the
+/// nearest food and advance one step along it; `Return` agents climb the
+/// pheromone gradient back toward home.
+pub(crate) fn step_agents(
+    agents: &mut [Agent],
+    matrix: &mut ElementMatrix,
+    pheromones: &mut PheromoneMap,
+    registry: &ElementRegistry,
+    food_id: ElementId,
+) {
+    for agent in agents.iter_mut() {
+        match agent.goal {
+            AgentGoal::Seek => {
+                if agent.path.is_empty() {
+                    if let Some(food) = nearest_food(matrix, agent.pos, food_id) {
+                        if let Some(mut path) = find_path(matrix, registry, agent.pos, food) {
+                            path.pop(); // drop the agent's current cell
+                            agent.path = path;
+                        }
+                    }
+                }
+
+                if let Some(next) = agent.path.pop() {
+                    agent.pos = next;
+                    pheromones.deposit(next, PHEROMONE_DEPOSIT);
+                }
+
+                let reached_food =
+                    matches!(&matrix.arr[agent.pos], Some(element) if element.flavor == food_id);
+                if reached_food {
+                    matrix.remove(agent.pos);
+                    agent.goal = AgentGoal::Return;
+                    agent.path.clear();
+                }
+            }
+            AgentGoal::Return => {
+                if agent.pos == agent.home {
+                    agent.goal = AgentGoal::Seek;
+                } else if let Some(next) = best_gradient_step(matrix, registry, pheromones, agent.pos, agent.home) {
+                    agent.pos = next;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! The boundary between the simulation and a concrete windowing surface.
+//!
+//! `sim` and `input` have no idea whether they're running in a native winit
+//! window or a browser canvas; a `Backend` is the thing that owns that
+//! surface, turns raw platform events into [`crate::input::InputEvent`]s, and
+//! blits a drawn frame back out to the screen.
+
+use crate::input::InputEvent;
+
+pub(crate) trait Backend {
+    /// Push a freshly drawn RGBA frame (`ARR_WIDTH * ARR_HEIGHT * 4` bytes) to
+    /// the screen.
+    fn present(&mut self, frame: &[u8]);
+
+    /// Drain whatever input has arrived since the last poll.
+    fn poll_input(&mut self) -> Vec<InputEvent>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod native;
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod wasm;
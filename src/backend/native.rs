@@ -0,0 +1,142 @@
+//! Native windowing backend: a winit window presented through `pixels`, with
+//! an egui overlay.
+
+use pixels::{Error, Pixels, SurfaceTexture};
+use winit::dpi::LogicalSize;
+use winit::event::{Event, VirtualKeyCode};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::window::{Window, WindowBuilder};
+use winit_input_helper::WinitInputHelper;
+
+use crate::backend::Backend;
+use crate::gui::Framework;
+use crate::input::{self, InputEvent, FOOD_ELEMENT, MOUSE_BUTTON_ELEMENTS};
+use crate::sim::{GameState, ARR_HEIGHT, ARR_WIDTH};
+
+const WIDTH: u32 = 320 * 4;
+const HEIGHT: u32 = 240 * 4;
+
+pub(crate) struct NativeBackend {
+    input: WinitInputHelper,
+    pixels: Pixels,
+    framework: Framework,
+    window: Window,
+}
+
+impl Backend for NativeBackend {
+    fn present(&mut self, frame: &[u8]) {
+        self.pixels.frame_mut().copy_from_slice(frame);
+        self.framework.prepare(&self.window);
+
+        let render_result = self.pixels.render_with(|encoder, render_target, context| {
+            context.scaling_renderer.render(encoder, render_target);
+            self.framework.render(encoder, render_target, context);
+            Ok(())
+        });
+
+        if let Err(err) = render_result {
+            crate::log_error("pixels.render", err);
+        }
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+
+        if self.input.key_pressed(VirtualKeyCode::Escape) || self.input.close_requested() {
+            events.push(InputEvent::Quit);
+            return events;
+        }
+
+        for (button, &element) in MOUSE_BUTTON_ELEMENTS.iter().enumerate() {
+            if self.input.mouse_held(button) {
+                if let Some(real_pos) = self.input.mouse() {
+                    events.push(InputEvent::Paint { element, at: input::pos_to_coord(real_pos) });
+                }
+            }
+        }
+
+        if self.input.key_held(VirtualKeyCode::F) {
+            if let Some(real_pos) = self.input.mouse() {
+                events.push(InputEvent::Paint { element: FOOD_ELEMENT, at: input::pos_to_coord(real_pos) });
+            }
+        }
+
+        if self.input.key_pressed(VirtualKeyCode::F5) {
+            events.push(InputEvent::Save);
+        }
+
+        if self.input.key_pressed(VirtualKeyCode::F9) {
+            events.push(InputEvent::Load);
+        }
+
+        events
+    }
+}
+
+pub(crate) fn run() -> Result<(), Error> {
+    let event_loop = EventLoop::new();
+    let mut input = WinitInputHelper::new();
+    let window = {
+        let size = LogicalSize::new(WIDTH, HEIGHT);
+        WindowBuilder::new()
+            .with_title("Hello Pixels + egui")
+            .with_inner_size(size)
+            .with_min_inner_size(size)
+            .build(&event_loop)
+            .unwrap()
+    };
+
+    let (pixels, framework) = {
+        let window_size = window.inner_size();
+        let scale_factor = window.scale_factor() as f32;
+        let surface_texture: SurfaceTexture<'_, Window> =
+            SurfaceTexture::new(window_size.width, window_size.height, &window);
+        let pixels = Pixels::new(ARR_WIDTH as u32, ARR_HEIGHT as u32, surface_texture)?;
+        let framework = Framework::new(&event_loop, window_size.width, window_size.height, scale_factor, &pixels);
+
+        (pixels, framework)
+    };
+
+    let mut backend = NativeBackend { input, pixels, framework, window };
+    let mut world = GameState::new();
+
+    event_loop.run(move |event, _, control_flow| {
+        if backend.input.update(&event) {
+            for input_event in backend.poll_input() {
+                if let InputEvent::Quit = input_event {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+                input::apply(&mut world, input_event);
+            }
+
+            if let Some(scale_factor) = backend.input.scale_factor() {
+                backend.framework.scale_factor(scale_factor);
+            }
+
+            if let Some(size) = backend.input.window_resized() {
+                if let Err(err) = backend.pixels.resize_surface(size.width, size.height) {
+                    crate::log_error("pixels.resize_surface", err);
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+                backend.framework.resize(size.width, size.height);
+            }
+
+            world.update();
+            backend.window.request_redraw();
+        }
+
+        match event {
+            Event::WindowEvent { event, .. } => {
+                backend.framework.handle_event(&event);
+            }
+            Event::RedrawRequested(_) => {
+                let mut frame = vec![0u8; ARR_WIDTH * ARR_HEIGHT * 4];
+                world.draw(&mut frame);
+                backend.present(&frame);
+            }
+            _ => (),
+        }
+    });
+}
@@ -0,0 +1,193 @@
+//! Browser backend: a `<canvas>` driven by `wasm-bindgen`/`web-sys`, with a
+//! `requestAnimationFrame` loop standing in for winit's event loop.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+
+use crate::backend::Backend;
+use crate::input::{self, InputEvent, FOOD_ELEMENT, MOUSE_BUTTON_ELEMENTS};
+use crate::sim::{GameState, ARR_HEIGHT, ARR_WIDTH};
+
+/// CSS pixels per grid cell, matching the divisor `input::pos_to_coord`
+/// uses to turn a cursor position into a cell.
+const SCALE: f64 = 10.0;
+
+pub(crate) struct CanvasBackend {
+    context: CanvasRenderingContext2d,
+    pending: Rc<RefCell<Vec<InputEvent>>>,
+    // Keep the closures alive for as long as the backend is; dropping them
+    // unregisters the listeners.
+    _listeners: Vec<Closure<dyn FnMut(web_sys::Event)>>,
+}
+
+impl Backend for CanvasBackend {
+    fn present(&mut self, frame: &[u8]) {
+        let mut rgba = frame.to_vec();
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            wasm_bindgen::Clamped(&mut rgba),
+            ARR_WIDTH as u32,
+            ARR_HEIGHT as u32,
+        )
+        .expect("frame buffer is the right size for the grid");
+        self.context
+            .put_image_data(&image, 0.0, 0.0)
+            .expect("canvas accepts the drawn frame");
+    }
+
+    fn poll_input(&mut self) -> Vec<InputEvent> {
+        self.pending.borrow_mut().drain(..).collect()
+    }
+}
+
+impl CanvasBackend {
+    fn new(canvas: &HtmlCanvasElement) -> Self {
+        // One canvas pixel per cell, so `present` can blit the frame as is;
+        // CSS then upscales each cell to SCALE pixels on screen.
+        canvas.set_width(ARR_WIDTH as u32);
+        canvas.set_height(ARR_HEIGHT as u32);
+        canvas
+            .set_attribute(
+                "style",
+                &format!("width: {}px; height: {}px;", ARR_WIDTH as f64 * SCALE, ARR_HEIGHT as f64 * SCALE),
+            )
+            .expect("canvas accepts a style attribute");
+
+        let context = canvas
+            .get_context("2d")
+            .expect("canvas supports a 2d context")
+            .expect("canvas 2d context is available")
+            .dyn_into::<CanvasRenderingContext2d>()
+            .expect("get_context(\"2d\") returns a CanvasRenderingContext2d");
+
+        let pending = Rc::new(RefCell::new(Vec::new()));
+        let listeners = install_event_listeners(canvas, &pending);
+
+        Self { context, pending, _listeners: listeners }
+    }
+}
+
+fn canvas_pos_to_coord(canvas: &HtmlCanvasElement, client_x: i32, client_y: i32) -> crate::sim::Coordinate {
+    let rect = canvas.get_bounding_client_rect();
+    let x = (client_x as f64 - rect.left()) as f32;
+    let y = (client_y as f64 - rect.top()) as f32;
+    input::pos_to_coord((x, y))
+}
+
+fn install_event_listeners(
+    canvas: &HtmlCanvasElement,
+    pending: &Rc<RefCell<Vec<InputEvent>>>,
+) -> Vec<Closure<dyn FnMut(web_sys::Event)>> {
+    let mut listeners = Vec::new();
+
+    // Tracked independently of button state so the "f" keydown handler
+    // below has somewhere to place Food under, the way `native.rs` polls
+    // the current cursor position every frame.
+    let last_mouse_pos = Rc::new(RefCell::new(None));
+    {
+        let canvas = canvas.clone();
+        let last_mouse_pos = last_mouse_pos.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            *last_mouse_pos.borrow_mut() = Some(canvas_pos_to_coord(&canvas, event.client_x(), event.client_y()));
+        }) as Box<dyn FnMut(web_sys::Event)>);
+        canvas
+            .add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())
+            .expect("canvas accepts a mousemove listener");
+        listeners.push(closure);
+    }
+
+    for (button, &element) in MOUSE_BUTTON_ELEMENTS.iter().enumerate() {
+        let canvas = canvas.clone();
+        let pending = pending.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let event = event.dyn_into::<web_sys::MouseEvent>().unwrap();
+            if event.buttons() & (1 << button) != 0 {
+                let at = canvas_pos_to_coord(&canvas, event.client_x(), event.client_y());
+                pending.borrow_mut().push(InputEvent::Paint { element, at });
+            }
+        }) as Box<dyn FnMut(web_sys::Event)>);
+        canvas
+            .add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())
+            .expect("canvas accepts a mousemove listener");
+        listeners.push(closure);
+    }
+
+    {
+        let window = web_sys::window().expect("a window exists in a browser context");
+        let pending = pending.clone();
+        let last_mouse_pos = last_mouse_pos.clone();
+        let closure = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let event = event.dyn_into::<web_sys::KeyboardEvent>().unwrap();
+            match event.key().as_str() {
+                "f" => {
+                    // Held-key painting isn't tracked here; a keydown is
+                    // enough to place one square under the last mouse
+                    // position the browser reported.
+                    if let Some(at) = *last_mouse_pos.borrow() {
+                        pending.borrow_mut().push(InputEvent::Paint { element: FOOD_ELEMENT, at });
+                    }
+                }
+                "F5" => {
+                    event.prevent_default();
+                    pending.borrow_mut().push(InputEvent::Save);
+                }
+                "F9" => {
+                    event.prevent_default();
+                    pending.borrow_mut().push(InputEvent::Load);
+                }
+                _ => {}
+            }
+        }) as Box<dyn FnMut(web_sys::Event)>);
+        window
+            .add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())
+            .expect("window accepts a keydown listener");
+        listeners.push(closure);
+    }
+
+    listeners
+}
+
+#[wasm_bindgen(start)]
+pub fn run() -> Result<(), JsValue> {
+    console_error_panic_hook::set_once();
+
+    let window = web_sys::window().expect("a window exists in a browser context");
+    let document = window.document().expect("window has a document");
+    let canvas = document
+        .get_element_by_id("bad-sand-game")
+        .expect("document has a canvas element with id \"bad-sand-game\"")
+        .dyn_into::<HtmlCanvasElement>()
+        .expect("#bad-sand-game is a <canvas>");
+
+    let mut backend = CanvasBackend::new(&canvas);
+    let mut world = GameState::new();
+    let mut frame = vec![0u8; ARR_WIDTH * ARR_HEIGHT * 4];
+
+    let f = Rc::new(RefCell::new(None));
+    let g = f.clone();
+
+    *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+        for event in backend.poll_input() {
+            input::apply(&mut world, event);
+        }
+        world.update();
+        world.draw(&mut frame);
+        backend.present(&frame);
+
+        request_animation_frame(f.borrow().as_ref().unwrap());
+    }) as Box<dyn FnMut()>));
+
+    request_animation_frame(g.borrow().as_ref().unwrap());
+    Ok(())
+}
+
+fn request_animation_frame(closure: &Closure<dyn FnMut()>) {
+    web_sys::window()
+        .expect("a window exists in a browser context")
+        .request_animation_frame(closure.as_ref().unchecked_ref())
+        .expect("requestAnimationFrame is available");
+}
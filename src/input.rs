@@ -0,0 +1,62 @@
+//! Backend-agnostic input events and how they affect the simulation.
+//!
+//! A `Backend` translates whatever raw input its platform gives it (winit
+//! events, DOM events) into these and hands them to [`apply`], so the same
+//! "hold mouse button 0 to paint Sand" logic doesn't need to be written twice.
+
+use crate::sim::{Coordinate, GameState, ARR_HEIGHT, ARR_WIDTH};
+
+/// The brush size used for all paint events, in cells.
+pub(crate) const BRUSH_SIZE: usize = 5;
+
+/// Element placed by each mouse button, by button index, matching the old
+/// native-only winit bindings: left/right/middle.
+pub(crate) const MOUSE_BUTTON_ELEMENTS: [&str; 3] = ["Sand", "Water", "Stone"];
+/// Element placed while the paint-food key is held.
+pub(crate) const FOOD_ELEMENT: &str = "Food";
+
+pub(crate) enum InputEvent {
+    /// Stamp a `BRUSH_SIZE`-ish square of `element` centered on `at`.
+    Paint { element: &'static str, at: Coordinate },
+    Save,
+    Load,
+    Quit,
+}
+
+/// Apply one `InputEvent` to `state`. Save/load failures are logged and
+/// otherwise ignored, same as the original inline winit handling.
+pub(crate) fn apply(state: &mut GameState, event: InputEvent) {
+    match event {
+        InputEvent::Paint { element, at } => {
+            let id = state.registry.id_of(element);
+            state.matrix.add_square(at, BRUSH_SIZE, id, state.framecount);
+        }
+        InputEvent::Save => {
+            if let Err(err) = state.save(crate::sim::SNAPSHOT_PATH) {
+                crate::log_error("GameState::save", err);
+            }
+        }
+        InputEvent::Load => {
+            if let Err(err) = state.load(crate::sim::SNAPSHOT_PATH) {
+                crate::log_error("GameState::load", err);
+            }
+        }
+        InputEvent::Quit => {}
+    }
+}
+
+fn clamp(val: f32, max: usize) -> usize {
+    if val < 0.0 {
+        0
+    } else if val > max as f32 {
+        max
+    } else {
+        val as usize
+    }
+}
+
+/// Map a raw cursor position (in physical pixels of the logical grid's
+/// upscale) to a grid `Coordinate`, clamped to the grid bounds.
+pub(crate) fn pos_to_coord(pos: (f32, f32)) -> Coordinate {
+    Coordinate { x: clamp(pos.0 / 10.0, ARR_WIDTH - 1), y: clamp(pos.1 / 10.0, ARR_HEIGHT - 1) }
+}
@@ -0,0 +1,184 @@
+//! Data-driven element definitions, loaded from a JSON5 config at startup.
+//!
+//! Elements used to be a hardcoded `ElementType` enum with their movesets spread
+//! across a big `match`. Now an element is just an `ElementId` (an index into the
+//! `Vec<ElementDef>` loaded here), so adding a new material is a config edit, not
+//! a recompile.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::sim::NegCoordinate;
+
+/// `ElementId(0)` is reserved for the empty/vacuum element; the loader enforces
+/// that the first entry in the config is named `"EMPTY"`.
+pub const EMPTY_ID: ElementId = ElementId(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ElementId(pub u32);
+
+/// A bitset over `ElementId`s, replacing the old `EnumSet<ElementType>`.
+/// Supports up to 64 distinct elements, which is plenty for a config file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ElementSet(u64);
+
+impl ElementSet {
+    fn of(id: ElementId) -> Self {
+        Self(1 << id.0)
+    }
+
+    pub fn contains(self, id: ElementId) -> bool {
+        self.0 & (1 << id.0) != 0
+    }
+}
+
+impl std::ops::BitOr for ElementSet {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+pub struct Move {
+    pub flavors: ElementSet,
+    pub directions: Vec<NegCoordinate>,
+}
+
+/// How `GameState::draw` varies a cell's base color from its neighbors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TintMode {
+    /// No variation: every cell of this element is the same color.
+    Flat,
+    /// A small per-cell brightness jitter, derived from a hash of the cell's
+    /// position and the element's spawn tick.
+    Noise,
+    /// Brightness varies smoothly with the cell's row.
+    VerticalGradient,
+}
+
+pub struct ElementDef {
+    pub name: String,
+    pub color: [u8; 4],
+    pub density: i32,
+    pub tint: TintMode,
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawElementDef {
+    name: String,
+    color: [u8; 4],
+    density: i32,
+    tint: TintMode,
+    moves: Vec<RawMoveDef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMoveDef {
+    flavors: Vec<String>,
+    directions: Vec<(i64, i64)>,
+}
+
+pub struct ElementRegistry {
+    defs: Vec<ElementDef>,
+}
+
+impl ElementRegistry {
+    /// Load and resolve a registry from a JSON5 file. Panics on a malformed
+    /// config: this only ever runs once at startup, so there's no recovery
+    /// path worth building yet.
+    pub fn load(path: &str) -> Self {
+        let text = std::fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("failed to read element config '{path}': {err}"));
+        let raw: Vec<RawElementDef> = json5::from_str(&text)
+            .unwrap_or_else(|err| panic!("failed to parse element config '{path}': {err}"));
+
+        assert!(
+            raw.first().is_some_and(|def| def.name == "EMPTY"),
+            "the first element in '{path}' must be named \"EMPTY\""
+        );
+        assert!(
+            raw.len() <= 64,
+            "'{path}' defines {} elements, but ElementSet packs flavors into a u64 bitset and can only hold 64",
+            raw.len()
+        );
+
+        let name_to_id: HashMap<&str, ElementId> = raw
+            .iter()
+            .enumerate()
+            .map(|(i, def)| (def.name.as_str(), ElementId(i as u32)))
+            .collect();
+
+        let defs = raw
+            .iter()
+            .map(|def| ElementDef {
+                name: def.name.clone(),
+                color: def.color,
+                density: def.density,
+                tint: def.tint,
+                moves: def.moves.iter().map(|m| resolve_move(m, &name_to_id)).collect(),
+            })
+            .collect();
+
+        Self { defs }
+    }
+
+    pub fn id_of(&self, name: &str) -> ElementId {
+        self.try_id_of(name)
+            .unwrap_or_else(|| panic!("unknown element '{name}'"))
+    }
+
+    /// Like [`ElementRegistry::id_of`], but `None` instead of a panic when
+    /// `name` isn't in this registry. Used to re-resolve names loaded from a
+    /// snapshot taken against a config that may have since changed.
+    pub fn try_id_of(&self, name: &str) -> Option<ElementId> {
+        self.defs
+            .iter()
+            .position(|def| def.name == name)
+            .map(|i| ElementId(i as u32))
+    }
+
+    pub fn name(&self, id: ElementId) -> &str {
+        &self.defs[id.0 as usize].name
+    }
+
+    pub fn moveset(&self, id: ElementId) -> &[Move] {
+        &self.defs[id.0 as usize].moves
+    }
+
+    pub fn color(&self, id: ElementId) -> [u8; 4] {
+        self.defs[id.0 as usize].color
+    }
+
+    pub fn density(&self, id: ElementId) -> i32 {
+        self.defs[id.0 as usize].density
+    }
+
+    pub fn tint(&self, id: ElementId) -> TintMode {
+        self.defs[id.0 as usize].tint
+    }
+
+    /// A "fluid" can be displaced: it has somewhere else to be.
+    pub fn is_fluid(&self, id: ElementId) -> bool {
+        id != EMPTY_ID && !self.defs[id.0 as usize].moves.is_empty()
+    }
+}
+
+fn resolve_move(raw: &RawMoveDef, name_to_id: &HashMap<&str, ElementId>) -> Move {
+    let flavors = raw.flavors.iter().fold(ElementSet::default(), |set, name| {
+        let id = *name_to_id
+            .get(name.as_str())
+            .unwrap_or_else(|| panic!("move references unknown element flavor '{name}'"));
+        set | ElementSet::of(id)
+    });
+
+    let directions = raw
+        .directions
+        .iter()
+        .map(|&(x, y)| NegCoordinate { x, y })
+        .collect();
+
+    Move { flavors, directions }
+}
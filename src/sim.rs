@@ -0,0 +1,448 @@
+//! The simulation core: the grid, its elements, the agents that walk over
+//! it, and the rules that step them forward one tick. Nothing in this module
+//! knows about a window, a canvas, or a keyboard — see `backend` for that.
+
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, IndexMut};
+use rand::seq::SliceRandom;
+
+use crate::agent::{self, Agent, PheromoneMap};
+use crate::registry::{ElementId, ElementRegistry, Move, TintMode, EMPTY_ID};
+use crate::save;
+
+pub(crate) const AGENT_COUNT: usize = 8;
+const AGENT_COLOR: [u8; 4] = [0xff, 0x00, 0xff, 0xff];
+
+pub(crate) const ELEMENT_CONFIG_PATH: &str = "elements.json5";
+pub(crate) const SNAPSHOT_PATH: &str = "snapshot.sand";
+
+const TICK_SPEED: u64 = 3;
+pub(crate) const ARR_WIDTH: usize = 320/2;
+pub(crate) const ARR_HEIGHT: usize = 240/2;
+
+/// Per-cell brightness offset used by `TintMode::Noise`, in `[-NOISE_STRENGTH, NOISE_STRENGTH]`.
+const NOISE_STRENGTH: i32 = 12;
+/// Total brightness swing of `TintMode::VerticalGradient`, top to bottom.
+const GRADIENT_STRENGTH: f32 = 40.0;
+
+/// A deterministic, stable-across-frames hash of a grain's position and
+/// spawn tick, used to seed its `Noise` jitter.
+fn cell_hash(x: usize, y: usize, spawn_tick: u64) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (x, y, spawn_tick).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn offset_brightness(base: [u8; 4], offset: i32) -> [u8; 4] {
+    let mut rgba = base;
+    for channel in &mut rgba[..3] {
+        *channel = (i32::from(*channel) + offset).clamp(0, 255) as u8;
+    }
+    rgba
+}
+
+fn tint_color(base: [u8; 4], tint: TintMode, x: usize, y: usize, spawn_tick: u64) -> [u8; 4] {
+    match tint {
+        TintMode::Flat => base,
+        TintMode::Noise => {
+            let jitter = cell_hash(x, y, spawn_tick) % (2 * NOISE_STRENGTH as u64 + 1);
+            offset_brightness(base, jitter as i32 - NOISE_STRENGTH)
+        }
+        TintMode::VerticalGradient => {
+            let t = y as f32 / ARR_HEIGHT as f32;
+            let offset = ((t - 0.5) * GRADIENT_STRENGTH) as i32;
+            offset_brightness(base, offset)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct NegCoordinate {
+    pub(crate) x: i64,
+    pub(crate) y: i64,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub(crate) struct Coordinate {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+}
+
+impl Coordinate {
+    fn in_bounds(self: &Self) -> bool {
+        if self.x < 0 || self.x >= ARR_WIDTH {
+            false
+        }
+        else if self.y < 0 || self.y  >= ARR_HEIGHT {
+            false
+        }
+        else {
+            true
+        }
+    }
+}
+
+impl From<Coordinate> for usize {
+    fn from(c: Coordinate) -> Self {
+        c.x + c.y * ARR_WIDTH
+    }
+}
+
+impl From<usize> for Coordinate {
+    fn from(i: usize) -> Self {
+        Coordinate{x: i % ARR_WIDTH, y: i / ARR_WIDTH}
+    }
+}
+
+// if this could be automatic it would be nice :(
+//impl From<NegCoordinate> for Coordinate {
+//    fn from(c: NegCoordinate) -> Self {
+//        Coordinate{x:c.x as usize, y:c.y as usize}
+//    }
+//}
+
+impl std::ops::Add for Coordinate {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl std::ops::Add<NegCoordinate> for Coordinate {
+    type Output = Self;
+
+    fn add(self, other: NegCoordinate) -> Self::Output {
+        Coordinate {
+            x: (self.x as i64 + other.x) as usize,
+            y: (self.y as i64 + other.y) as usize,
+        }
+    }
+}
+
+/// Walks every cell bottom-to-top, alternating the within-row direction by
+/// tick parity. A fixed scan order lets cells processed earlier in a row
+/// claim moves before their neighbors get a turn, which shows up as a
+/// horizontal drift in piling sand and spreading water; alternating left-to-
+/// right and right-to-left every other tick cancels that bias out.
+pub(crate) struct CoordinateIterator {
+    y: usize,
+    x: usize,
+    reverse: bool,
+    done: bool,
+}
+
+impl CoordinateIterator {
+    /// `tick` is the simulation tick counter (see `GameState::update`), not
+    /// the raw frame count: even ticks scan each row left-to-right, odd
+    /// ticks right-to-left.
+    fn new(tick: u64) -> Self {
+        Self {
+            y: ARR_HEIGHT - 1,
+            x: 0,
+            reverse: tick % 2 != 0,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for CoordinateIterator {
+    type Item = Coordinate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let x = if self.reverse { ARR_WIDTH - 1 - self.x } else { self.x };
+        let coord = Coordinate { x, y: self.y };
+
+        self.x += 1;
+        if self.x == ARR_WIDTH {
+            self.x = 0;
+            if self.y == 0 {
+                self.done = true;
+            } else {
+                self.y -= 1;
+            }
+        }
+
+        Some(coord)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct Element {
+    position: Coordinate,
+    pub(crate) flavor: ElementId,
+    /// The tick this element was placed, hashed together with the cell's
+    /// position to seed its `Noise` tint jitter, so grains placed on
+    /// different ticks differ in shade. The position is part of the hash, so
+    /// a grain's shade changes when it moves to another cell.
+    pub(crate) spawn_tick: u64,
+}
+
+impl Element {
+    pub(crate) fn new(position: Coordinate, flavor: ElementId, spawn_tick: u64) -> Self {
+        Self {
+            position,
+            flavor,
+            spawn_tick,
+        }
+    }
+}
+
+pub(crate) struct ElementMatrix {
+    pub(crate) arr: Matrix,
+    new_arr: Matrix,
+}
+
+
+
+impl ElementMatrix {
+    fn new() -> Self {
+        Self {
+            arr: Matrix::new(),
+            new_arr: Matrix::new(),
+        }
+    }
+
+    fn add(self: &mut Self, element: Element) {
+        let pos = (&element).position;
+        self.new_arr[pos] = Some(element)
+    }
+
+    // TODO: i would prefer this. why does it not work??
+    //fn add(self: &mut Self, element: Element) {
+    //    self[(&element).position] = Some(element);
+    //}
+
+
+    pub(crate) fn add_square(self: &mut Self, coord: Coordinate, size: usize, flavor: ElementId, spawn_tick: u64) {
+        for offset_x in coord.x-usize::div_ceil(size,2)..coord.x+(size/2) {
+            for offset_y in coord.y-usize::div_ceil(size,2)..coord.y+(size/2) {
+                self.add(Element::new(Coordinate{x:offset_x,y:offset_y},flavor,spawn_tick));
+            }
+
+        }
+
+    }
+
+    // TODO: none can mean either nothing or out of bounds...
+    // this is confusing i think
+    fn get_from_new(self: &Self, index: Coordinate) -> Option<&Option<Element>> {
+        if index.in_bounds() {
+            Some(&self.new_arr[index])
+        } else {
+            None
+        }
+    }
+
+    fn move_to(self: &mut Self, a: Coordinate, b: Coordinate) {
+        if b.in_bounds() {
+            self.new_arr.swap(a, b)
+        }
+    }
+
+    /// Remove whatever is at `pos`, in both the current grid and the
+    /// in-progress next-tick buffer. A plain `self.arr[pos] = None` only
+    /// clears the half of the double buffer `step`/`finish_update` aren't
+    /// about to overwrite, so the old value comes back on the next swap;
+    /// agents eating food need to clear both at once.
+    pub(crate) fn remove(self: &mut Self, pos: Coordinate) {
+        self.arr[pos] = None;
+        self.new_arr[pos] = None;
+    }
+
+    fn attempt_directions(self: &mut Self, a: Coordinate, flavor: ElementId, moves: &[Move], registry: &ElementRegistry) {
+        for _move in moves {
+            let new_pos = a+*_move.directions.choose(&mut rand::thread_rng()).unwrap();
+            if let Some(does_element_exist) = self.get_from_new(new_pos) {
+                match does_element_exist {
+                    Some(new_element) => {
+                        let displaces = registry.is_fluid(new_element.flavor)
+                            && registry.density(new_element.flavor) < registry.density(flavor);
+                        if _move.flavors.contains(new_element.flavor) || displaces {
+                            self.move_to(a,new_pos);
+                            return;
+                        }
+                    },
+                    None => {
+                        if _move.flavors.contains(EMPTY_ID) {
+                            self.move_to(a,new_pos);
+                            return;
+                        }
+                    },
+                }
+
+            }
+        }
+    }
+
+    fn step(self: &mut Self, a: Coordinate, registry: &ElementRegistry) {
+        if let Some(element) = &self.arr[a] {
+            let moves = registry.moveset(element.flavor);
+            self.attempt_directions(a, element.flavor, moves, registry)
+        }
+    }
+
+    fn finish_update(self: &mut Self) {
+        unsafe {
+            let a: *mut Matrix = &mut self.new_arr;
+            let b: *mut Matrix = &mut self.arr;
+            std::ptr::swap(a, b);
+            *a = *b.clone();
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct Matrix {
+    pub(crate) arr: [Option<Element>; ARR_WIDTH * ARR_HEIGHT]
+}
+
+const EMPTY: Option<Element> = None;
+
+impl Matrix {
+    fn swap(self: &mut Self, a:Coordinate, b:Coordinate) {
+        self.arr.swap(a.into(), b.into())
+    }
+
+    pub(crate) fn new() -> Self {
+        Self {
+            arr: [EMPTY; ARR_WIDTH * ARR_HEIGHT]
+        }
+    }
+}
+
+impl Index<Coordinate> for Matrix {
+    type Output = Option<Element>;
+
+    fn index(&self, index: Coordinate) -> &Self::Output {
+        return &self.arr[usize::from(index)]
+    }
+}
+
+impl IndexMut<Coordinate> for Matrix {
+    fn index_mut(&mut self, index: Coordinate) -> &mut Self::Output {
+        return &mut self.arr[usize::from(index)]
+    }
+}
+
+pub(crate) struct GameState {
+    pub(crate) matrix: ElementMatrix,
+    pub(crate) registry: ElementRegistry,
+    agents: Vec<Agent>,
+    pheromones: PheromoneMap,
+    food_id: ElementId,
+    pub(crate) framecount: u64,
+}
+
+
+
+impl GameState {
+    /// Create a new `World` instance that can draw a moving box.
+    pub(crate) fn new() -> Self {
+        let registry = ElementRegistry::load(ELEMENT_CONFIG_PATH);
+        let food_id = registry.id_of("Food");
+        let home = Coordinate { x: ARR_WIDTH / 2, y: ARR_HEIGHT - 1 };
+
+        Self {
+            matrix: ElementMatrix::new(),
+            registry,
+            agents: (0..AGENT_COUNT).map(|_| Agent::new(home)).collect(),
+            pheromones: PheromoneMap::new(),
+            food_id,
+            framecount: 0,
+        }
+    }
+
+    /// Update the `World` internal state; bounce the box around the screen.
+    pub(crate) fn update(&mut self) {
+        /*if self.box_x <= 0 || self.box_x + BOX_SIZE > WIDTH as i16 {
+            self.velocity_x *= -1;
+        }
+        if self.box_y <= 0 || self.box_y + BOX_SIZE > HEIGHT as i16 {
+            self.velocity_y *= -1;
+        }
+
+        self.box_x += self.velocity_x;
+        self.box_y += self.velocity_y;*/
+        self.framecount += 1;
+
+        if self.framecount % TICK_SPEED == 0 {
+            let tick = self.framecount / TICK_SPEED;
+            for index in CoordinateIterator::new(tick) {
+                self.matrix.step(index, &self.registry)
+            }
+            self.matrix.finish_update();
+
+            agent::step_agents(&mut self.agents, &mut self.matrix, &mut self.pheromones, &self.registry, self.food_id);
+            self.pheromones.tick();
+        }
+
+        /*
+        if self.framecount % (TICK_SPEED*300) == 0 {
+            let coord = Coordinate{
+                x:rand::thread_rng().gen_range(20..25),
+                y:rand::thread_rng().gen_range(11..15)};
+            self.matrix.add_square(coord,15,ElementType::Sand)
+        }
+
+        if self.framecount % (TICK_SPEED) == 0 {
+            let coord = Coordinate{
+                x:rand::thread_rng().gen_range(10..15),
+                y:rand::thread_rng().gen_range(11..15)};
+            self.matrix.add_square(coord,10,ElementType::Water)
+        }
+        */
+
+    }
+
+    /// Write a run-length encoded snapshot of the grid to `path`. See the
+    /// [`save`] module for the on-disk format.
+    pub(crate) fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        save::save(&self.matrix.arr, &self.registry, path)
+    }
+
+    /// Replace the grid with a snapshot previously written by [`GameState::save`].
+    /// A snapshot taken at a different grid size is letterboxed into the
+    /// current grid rather than rejected outright. Elements are resolved by
+    /// name against the live registry, so a snapshot saved under a different
+    /// `elements.json5` loads safely: unknown names become empty cells
+    /// instead of panicking.
+    pub(crate) fn load(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        // Set both halves of the double buffer, as `ElementMatrix::remove`
+        // does, so the next `finish_update` doesn't bring the old grid back.
+        let loaded = save::load(&self.registry, path)?;
+        self.matrix.arr = loaded;
+        self.matrix.new_arr = loaded;
+        Ok(())
+    }
+
+    pub(crate) fn draw(&self, frame: &mut [u8]) {
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let x = i % ARR_WIDTH;
+            let y = i / ARR_WIDTH;
+
+            let maybe_element = &self.matrix.arr[Coordinate{x,y}];
+
+            let (flavor, spawn_tick) = match maybe_element {
+                None => (EMPTY_ID, 0),
+                Some(element) => (element.flavor, element.spawn_tick),
+            };
+            let rgba = tint_color(self.registry.color(flavor), self.registry.tint(flavor), x, y, spawn_tick);
+
+            pixel.copy_from_slice(&rgba);
+        }
+
+        // Agents don't live in the matrix, so paint them over the top.
+        for agent in &self.agents {
+            let i = usize::from(agent.pos);
+            frame[i*4..i*4+4].copy_from_slice(&AGENT_COLOR);
+        }
+    }
+}